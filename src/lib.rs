@@ -0,0 +1,26 @@
+pub mod network;
+pub mod os;
+
+use ::pnet_bandwhich_fork::datalink::{DataLinkReceiver, NetworkInterface};
+
+use crate::network::dns;
+use crate::network::{InterfaceUpdate, LinkLayer, LocalSocket};
+use crate::os::shared::{OnSigWinch, SigCleanup};
+
+pub type OpenSockets = ::std::collections::HashMap<LocalSocket, network::ProcessInfo>;
+
+pub struct OsInputOutput {
+    pub network_interfaces: Vec<NetworkInterface>,
+    pub network_frames: Vec<(LinkLayer, Box<dyn DataLinkReceiver>)>,
+    /// Live hotplug feed for interfaces that come up or go down after
+    /// startup. `None` on platforms without a watcher subsystem and in pcap
+    /// replay mode; the capture loop keeps running off the initial
+    /// `network_frames` snapshot in that case.
+    pub interface_updates: Option<::std::sync::mpsc::Receiver<InterfaceUpdate>>,
+    pub get_open_sockets: fn() -> OpenSockets,
+    pub keyboard_events: Box<dyn Iterator<Item = ::termion::event::Event>>,
+    pub dns_client: Option<dns::Client>,
+    pub on_winch: Box<OnSigWinch>,
+    pub cleanup: Box<SigCleanup>,
+    pub write_to_stdout: Box<dyn FnMut(String) + Send>,
+}
@@ -0,0 +1,30 @@
+use ::std::net::IpAddr;
+
+use ::tokio::runtime::Runtime;
+
+pub struct Resolver;
+
+impl Resolver {
+    pub async fn new(_handle: ::tokio::runtime::Handle) -> Result<Self, failure::Error> {
+        Ok(Resolver)
+    }
+
+    pub fn lookup(&self, _ip: IpAddr) -> Option<String> {
+        None
+    }
+}
+
+pub struct Client {
+    resolver: Resolver,
+    runtime: Runtime,
+}
+
+impl Client {
+    pub fn new(resolver: Resolver, runtime: Runtime) -> Result<Self, failure::Error> {
+        Ok(Client { resolver, runtime })
+    }
+
+    pub fn resolve(&mut self, ip: IpAddr) -> Option<String> {
+        self.runtime.block_on(async { self.resolver.lookup(ip) })
+    }
+}
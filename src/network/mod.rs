@@ -0,0 +1,61 @@
+pub mod dns;
+
+use ::std::net::IpAddr;
+
+use ::pnet_bandwhich_fork::datalink::{DataLinkReceiver, NetworkInterface};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl ::std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self {
+            Protocol::Tcp => write!(f, "tcp"),
+            Protocol::Udp => write!(f, "udp"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocalSocket {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub protocol: Protocol,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProcessInfo {
+    pub name: String,
+    pub pid: i32,
+}
+
+/// Framing of the raw frames a `DataLinkReceiver` yields for a given
+/// interface, so the packet parser knows how many bytes to strip (if any)
+/// before it reaches the IP header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkLayer {
+    /// A 14-byte Ethernet header precedes the IP header.
+    Ethernet,
+    /// No link-layer framing: the first byte is the start of the IP header.
+    /// This is what loopback and tun/tap interfaces give us.
+    Raw,
+    /// Linux's `any` pseudo-device: a fixed 16-byte cooked-capture (`SLL`)
+    /// header precedes the IP header.
+    Cooked,
+}
+
+/// A hotplug event for the live interface registry: an interface came up
+/// (with a freshly opened capture channel) or went down.
+pub enum InterfaceUpdate {
+    Up {
+        interface: NetworkInterface,
+        link_layer: LinkLayer,
+        frames: Box<dyn DataLinkReceiver>,
+    },
+    Down {
+        interface_name: String,
+    },
+}
@@ -0,0 +1,19 @@
+mod proc_net;
+mod rtnetlink_watcher;
+mod sock_diag;
+
+pub use rtnetlink_watcher::spawn_interface_watcher;
+
+use crate::OpenSockets;
+
+/// Enumerates open sockets on Linux, preferring the `NETLINK_SOCK_DIAG` path
+/// and falling back to parsing `/proc/net` if that path fails for any
+/// reason, e.g. because the netlink socket can't be opened when `sock_diag`
+/// is disabled in this kernel/container. Both paths attribute sockets to
+/// processes by scanning `/proc/<pid>/fd`.
+pub fn get_open_sockets() -> OpenSockets {
+    match sock_diag::get_open_sockets() {
+        Ok(open_sockets) => open_sockets,
+        Err(_) => proc_net::get_open_sockets(),
+    }
+}
@@ -0,0 +1,68 @@
+use ::std::collections::HashMap;
+
+use ::procfs::net::{tcp, tcp6, udp, udp6};
+use ::procfs::process::{all_processes, FDTarget};
+
+use crate::network::{LocalSocket, Protocol};
+use crate::OpenSockets;
+
+/// Enumerates open sockets by reading `/proc/net/{tcp,tcp6,udp,udp6}` for the
+/// 4-tuples and `/proc/<pid>/fd/*` for the `socket:[inode]` symlinks that tie
+/// them back to a process. This is the original approach, kept as a
+/// fallback for kernels/containers where the `sock_diag` netlink family
+/// isn't available; only the source of the 4-tuples differs from it.
+pub fn get_open_sockets() -> OpenSockets {
+    let mut open_sockets = OpenSockets::new();
+    let mut inode_to_process: HashMap<u64, crate::network::ProcessInfo> = HashMap::new();
+
+    let all_procs = match all_processes() {
+        Ok(procs) => procs,
+        Err(_) => return open_sockets,
+    };
+
+    for process in all_procs {
+        let process = match process {
+            Ok(process) => process,
+            Err(_) => continue,
+        };
+        let (stat, fds) = match (process.stat(), process.fd()) {
+            (Ok(stat), Ok(fds)) => (stat, fds),
+            _ => continue,
+        };
+        let info = crate::network::ProcessInfo {
+            name: stat.comm,
+            pid: stat.pid,
+        };
+        for fd in fds.into_iter().flatten() {
+            if let FDTarget::Socket(inode) = fd.target {
+                inode_to_process.insert(inode, info.clone());
+            }
+        }
+    }
+
+    let tcp_entries = tcp().unwrap_or_default().into_iter().chain(tcp6().unwrap_or_default());
+    for entry in tcp_entries {
+        if let Some(info) = inode_to_process.get(&entry.inode) {
+            let local_socket = LocalSocket {
+                ip: entry.local_address.ip(),
+                port: entry.local_address.port(),
+                protocol: Protocol::Tcp,
+            };
+            open_sockets.insert(local_socket, info.clone());
+        }
+    }
+
+    let udp_entries = udp().unwrap_or_default().into_iter().chain(udp6().unwrap_or_default());
+    for entry in udp_entries {
+        if let Some(info) = inode_to_process.get(&entry.inode) {
+            let local_socket = LocalSocket {
+                ip: entry.local_address.ip(),
+                port: entry.local_address.port(),
+                protocol: Protocol::Udp,
+            };
+            open_sockets.insert(local_socket, info.clone());
+        }
+    }
+
+    open_sockets
+}
@@ -0,0 +1,267 @@
+use ::std::collections::HashSet;
+use ::std::io;
+use ::std::sync::mpsc::{channel, Receiver, Sender};
+use ::std::thread;
+
+use ::netlink_packet_core::{NetlinkMessage, NetlinkPayload};
+use ::netlink_packet_route::constants::{IFF_RUNNING, IFF_UP};
+use ::netlink_packet_route::link::nlas::Nla as LinkNla;
+use ::netlink_packet_route::{LinkMessage, RtnlMessage};
+use ::netlink_sys::constants::{RTMGRP_IPV4_IFADDR, RTMGRP_IPV6_IFADDR, RTMGRP_LINK};
+use ::netlink_sys::{protocols::NETLINK_ROUTE, Socket, SocketAddr};
+use ::pnet_bandwhich_fork::datalink;
+
+use crate::network::InterfaceUpdate;
+use crate::os::shared::get_datalink_channel;
+
+const GROUPS: u32 = RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR;
+
+/// Spawns a background thread that subscribes to the `NETLINK_ROUTE`
+/// link/address multicast groups and opens (or tears down) a datalink
+/// channel for each interface as it comes up or goes down, so a
+/// long-running `bandwhich` session picks up VPN connects, USB tethering
+/// and Wi-Fi reassociation without needing a restart.
+pub fn spawn_interface_watcher(
+    already_open: impl IntoIterator<Item = String> + Send + 'static,
+) -> Receiver<InterfaceUpdate> {
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        if let Ok(socket) = open_route_socket() {
+            watch(socket, tx, already_open.into_iter().collect());
+        }
+    });
+    rx
+}
+
+fn open_route_socket() -> io::Result<Socket> {
+    let mut socket = Socket::new(NETLINK_ROUTE)?;
+    socket.bind(&SocketAddr::new(0, GROUPS))?;
+    Ok(socket)
+}
+
+fn watch(socket: Socket, tx: Sender<InterfaceUpdate>, mut open_interfaces: HashSet<String>) {
+    // `open_interfaces` is seeded with the interfaces the static startup
+    // snapshot already opened, so a later DelLink for one of them actually
+    // tears its channel down, and a later NewAddress/NewLink (DHCP renewal,
+    // Wi-Fi reassociation) doesn't open a second channel for it.
+    let mut buf = vec![0; 8192];
+    loop {
+        let n = match socket.recv(&mut &mut buf[..], 0) {
+            Ok(n) => n,
+            Err(_) => return,
+        };
+        let mut offset = 0;
+        while offset < n {
+            let message = match <NetlinkMessage<RtnlMessage>>::deserialize(&buf[offset..n]) {
+                Ok(message) => message,
+                Err(_) => break,
+            };
+            offset += message.header.length as usize;
+            handle_message(message, &tx, &mut open_interfaces);
+        }
+    }
+}
+
+fn handle_message(
+    message: NetlinkMessage<RtnlMessage>,
+    tx: &Sender<InterfaceUpdate>,
+    open_interfaces: &mut HashSet<String>,
+) {
+    let name = match message.payload {
+        NetlinkPayload::InnerMessage(RtnlMessage::NewLink(link)) => {
+            let name = match link_name(&link) {
+                Some(name) => name,
+                None => return,
+            };
+            // A link that's still present but went administratively or
+            // carrier down (`ip link set down`, Wi-Fi disassociation) is
+            // reported as NewLink with IFF_UP/IFF_RUNNING cleared, not as
+            // DelLink -- that only fires when the netdev itself is torn
+            // down (unplugged USB adapter, driver unbind).
+            if open_interfaces.contains(&name) {
+                if !link_is_up(&link) {
+                    open_interfaces.remove(&name);
+                    let _ = tx.send(InterfaceUpdate::Down {
+                        interface_name: name,
+                    });
+                }
+                return;
+            }
+            Some(name)
+        }
+        NetlinkPayload::InnerMessage(RtnlMessage::NewAddress(addr)) => {
+            interface_name_for_index(addr.header.index)
+        }
+        NetlinkPayload::InnerMessage(RtnlMessage::DelLink(link)) => {
+            if let Some(name) = link_name(&link) {
+                if open_interfaces.remove(&name) {
+                    let _ = tx.send(InterfaceUpdate::Down {
+                        interface_name: name,
+                    });
+                }
+            }
+            return;
+        }
+        // A removed address doesn't necessarily mean the interface is gone;
+        // it may still be usable via another address. DelLink, or a NewLink
+        // with the link down, is what tells us to tear the channel down.
+        _ => return,
+    };
+
+    let name = match name {
+        Some(name) => name,
+        None => return,
+    };
+    if open_interfaces.contains(&name) {
+        return;
+    }
+    let interface = match find_interface(&name) {
+        Some(interface) => interface,
+        None => return,
+    };
+    if !interface.is_up() || interface.ips.is_empty() {
+        return;
+    }
+    if let Ok((link_layer, frames)) = get_datalink_channel(&interface) {
+        open_interfaces.insert(name);
+        let _ = tx.send(InterfaceUpdate::Up {
+            interface,
+            link_layer,
+            frames,
+        });
+    }
+}
+
+fn link_name(link: &LinkMessage) -> Option<String> {
+    link.nlas.iter().find_map(|nla| match nla {
+        LinkNla::IfName(name) => Some(name.clone()),
+        _ => None,
+    })
+}
+
+/// A link is only usable for capture while both `IFF_UP` (administratively
+/// up) and `IFF_RUNNING` (carrier present) are set; either one dropping
+/// means the existing `DataLinkReceiver` for it has gone stale.
+fn link_is_up(link: &LinkMessage) -> bool {
+    let flags = link.header.flags;
+    flags & IFF_UP as u32 != 0 && flags & IFF_RUNNING as u32 != 0
+}
+
+/// Resolves an address message's ifindex to an interface name. `IFA_LABEL`
+/// would be simpler, but the kernel only ever emits it for IPv4 addresses
+/// (`inet6_fill_ifaddr` has no label field), and we subscribe to
+/// `RTMGRP_IPV6_IFADDR` too, so relying on it would silently drop every
+/// IPv6 `NewAddress` event.
+fn interface_name_for_index(index: u32) -> Option<String> {
+    datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.index == index)
+        .map(|iface| iface.name)
+}
+
+fn find_interface(name: &str) -> Option<datalink::NetworkInterface> {
+    datalink::interfaces().into_iter().find(|iface| iface.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::std::sync::mpsc::TryRecvError;
+
+    use ::netlink_packet_core::NetlinkHeader;
+
+    fn link_message(name: &str, flags: u32) -> LinkMessage {
+        let mut link = LinkMessage::default();
+        link.header.flags = flags;
+        link.nlas.push(LinkNla::IfName(name.to_string()));
+        link
+    }
+
+    fn new_link(name: &str, flags: u32) -> NetlinkMessage<RtnlMessage> {
+        NetlinkMessage::new(
+            NetlinkHeader::default(),
+            NetlinkPayload::InnerMessage(RtnlMessage::NewLink(link_message(name, flags))),
+        )
+    }
+
+    fn del_link(name: &str) -> NetlinkMessage<RtnlMessage> {
+        NetlinkMessage::new(
+            NetlinkHeader::default(),
+            NetlinkPayload::InnerMessage(RtnlMessage::DelLink(link_message(name, 0))),
+        )
+    }
+
+    #[test]
+    fn link_is_up_requires_both_iff_up_and_iff_running() {
+        let up = link_message("eth0", IFF_UP as u32 | IFF_RUNNING as u32);
+        assert!(link_is_up(&up));
+
+        let administratively_down = link_message("eth0", IFF_RUNNING as u32);
+        assert!(!link_is_up(&administratively_down));
+
+        let no_carrier = link_message("eth0", IFF_UP as u32);
+        assert!(!link_is_up(&no_carrier));
+
+        let fully_down = link_message("eth0", 0);
+        assert!(!link_is_up(&fully_down));
+    }
+
+    #[test]
+    fn new_link_with_flags_cleared_sends_down_for_an_open_interface() {
+        let (tx, rx) = channel();
+        let mut open_interfaces: HashSet<String> = ["eth0".to_string()].into_iter().collect();
+
+        handle_message(new_link("eth0", 0), &tx, &mut open_interfaces);
+
+        assert!(!open_interfaces.contains("eth0"));
+        match rx.try_recv() {
+            Ok(InterfaceUpdate::Down { interface_name }) => assert_eq!(interface_name, "eth0"),
+            other => panic!("expected InterfaceUpdate::Down, got {:?}", other.is_ok()),
+        }
+    }
+
+    #[test]
+    fn new_link_still_up_is_a_no_op_for_an_open_interface() {
+        let (tx, rx) = channel();
+        let mut open_interfaces: HashSet<String> = ["eth0".to_string()].into_iter().collect();
+
+        handle_message(
+            new_link("eth0", IFF_UP as u32 | IFF_RUNNING as u32),
+            &tx,
+            &mut open_interfaces,
+        );
+
+        assert!(open_interfaces.contains("eth0"));
+        assert_eq!(rx.try_recv().unwrap_err(), TryRecvError::Empty);
+    }
+
+    #[test]
+    fn del_link_removes_and_sends_down_for_an_open_interface() {
+        let (tx, rx) = channel();
+        let mut open_interfaces: HashSet<String> = ["eth0".to_string()].into_iter().collect();
+
+        handle_message(del_link("eth0"), &tx, &mut open_interfaces);
+
+        assert!(open_interfaces.is_empty());
+        match rx.try_recv() {
+            Ok(InterfaceUpdate::Down { interface_name }) => assert_eq!(interface_name, "eth0"),
+            other => panic!("expected InterfaceUpdate::Down, got {:?}", other.is_ok()),
+        }
+    }
+
+    #[test]
+    fn del_link_for_an_interface_we_never_opened_is_a_no_op() {
+        let (tx, rx) = channel();
+        let mut open_interfaces: HashSet<String> = HashSet::new();
+
+        handle_message(del_link("eth0"), &tx, &mut open_interfaces);
+
+        assert!(open_interfaces.is_empty());
+        assert_eq!(rx.try_recv().unwrap_err(), TryRecvError::Empty);
+    }
+
+    #[test]
+    fn interface_name_for_index_returns_none_for_an_index_that_does_not_exist() {
+        assert_eq!(interface_name_for_index(u32::MAX), None);
+    }
+}
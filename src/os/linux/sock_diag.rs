@@ -0,0 +1,131 @@
+use ::std::collections::HashMap;
+use ::std::io;
+use ::std::net::IpAddr;
+
+use ::netlink_packet_core::{NetlinkMessage, NetlinkPayload, NLM_F_DUMP, NLM_F_REQUEST};
+use ::netlink_packet_sock_diag::{
+    constants::{AF_INET, AF_INET6, IPPROTO_TCP, IPPROTO_UDP},
+    inet::{ExtensionFlags, InetRequest, SocketId},
+    SockDiagMessage,
+};
+use ::netlink_sys::{protocols::NETLINK_SOCK_DIAG, Socket, SocketAddr};
+use ::procfs::process::{all_processes, FDTarget};
+
+use crate::network::{LocalSocket, Protocol, ProcessInfo};
+use crate::OpenSockets;
+
+/// Address families we dump sockets for.
+const FAMILIES: [u8; 2] = [AF_INET, AF_INET6];
+/// Protocols we dump sockets for, paired with the `Protocol` we tag the
+/// resulting `LocalSocket`s with.
+const PROTOCOLS: [(u8, Protocol); 2] = [(IPPROTO_TCP, Protocol::Tcp), (IPPROTO_UDP, Protocol::Udp)];
+
+/// Enumerates open sockets by asking the kernel directly over
+/// `NETLINK_SOCK_DIAG`, rather than shelling out or reading `/proc/net`.
+/// Only requires `cap_net_raw` (to open the netlink socket) plus whatever
+/// is needed to read our own `/proc/<pid>/fd` entries, which is nothing
+/// extra for processes we own and `cap_dac_read_search` for everyone else's.
+pub fn get_open_sockets() -> io::Result<OpenSockets> {
+    let inode_to_process = build_inode_to_process_map();
+
+    let mut socket = Socket::new(NETLINK_SOCK_DIAG)?;
+    socket.connect(&SocketAddr::new(0, 0))?;
+
+    let mut open_sockets = OpenSockets::new();
+    for &family in &FAMILIES {
+        for &(protocol, tagged_protocol) in &PROTOCOLS {
+            dump_one(&socket, family, protocol, tagged_protocol, &inode_to_process, &mut open_sockets)?;
+        }
+    }
+    Ok(open_sockets)
+}
+
+fn dump_one(
+    socket: &Socket,
+    family: u8,
+    protocol: u8,
+    tagged_protocol: Protocol,
+    inode_to_process: &HashMap<u32, ProcessInfo>,
+    open_sockets: &mut OpenSockets,
+) -> io::Result<()> {
+    let mut request = NetlinkMessage::from(SockDiagMessage::InetRequest(InetRequest {
+        family,
+        protocol,
+        extensions: ExtensionFlags::empty(),
+        states: u32::MAX,
+        socket_id: SocketId::new_v4(),
+    }));
+    request.header.flags = NLM_F_REQUEST | NLM_F_DUMP;
+    request.finalize();
+
+    let mut buf = vec![0; request.header.length as usize];
+    request.serialize(&mut buf);
+    socket.send(&buf, 0)?;
+
+    let mut receive_buf = vec![0; 8192];
+    'outer: loop {
+        let n = socket.recv(&mut &mut receive_buf[..], 0)?;
+        let mut offset = 0;
+        while offset < n {
+            let bytes = &receive_buf[offset..n];
+            let rx_packet = <NetlinkMessage<SockDiagMessage>>::deserialize(bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            match rx_packet.payload {
+                NetlinkPayload::Done(_) => break 'outer,
+                NetlinkPayload::Error(err) => return Err(io::Error::from_raw_os_error(-err.code)),
+                NetlinkPayload::InnerMessage(SockDiagMessage::InetResponse(response)) => {
+                    let inode = response.header.inode;
+                    if let Some(info) = inode_to_process.get(&inode) {
+                        let ip: IpAddr = response.header.socket_id.source_address;
+                        let local_socket = LocalSocket {
+                            ip,
+                            port: response.header.socket_id.source_port,
+                            protocol: tagged_protocol,
+                        };
+                        open_sockets.insert(local_socket, info.clone());
+                    }
+                }
+                _ => {}
+            }
+            offset += rx_packet.header.length as usize;
+        }
+    }
+    Ok(())
+}
+
+/// Scans `/proc/<pid>/fd/*` for `socket:[inode]` targets, which is readable
+/// for our own processes without any extra capability and for everyone
+/// else's with `cap_dac_read_search`. The `/proc/net` fallback attributes
+/// sockets to processes with the same scan.
+fn build_inode_to_process_map() -> HashMap<u32, ProcessInfo> {
+    let mut inode_to_process = HashMap::new();
+    let all_procs = match all_processes() {
+        Ok(procs) => procs,
+        Err(_) => return inode_to_process,
+    };
+
+    for process in all_procs {
+        let process = match process {
+            Ok(process) => process,
+            Err(_) => continue,
+        };
+        let (stat, fds) = match (process.stat(), process.fd()) {
+            (Ok(stat), Ok(fds)) => (stat, fds),
+            _ => continue,
+        };
+        let info = ProcessInfo {
+            name: stat.comm,
+            pid: stat.pid,
+        };
+        for fd in fds.into_iter().flatten() {
+            if let FDTarget::Socket(inode) = fd.target {
+                // procfs reports the inode as u64, but this narrows to u32
+                // on purpose: `inet_diag_msg.idiag_inode`, what we look
+                // `inode` up by, is a 32-bit field in the netlink ABI, so a
+                // wider key here could never match.
+                inode_to_process.insert(inode as u32, info.clone());
+            }
+        }
+    }
+    inode_to_process
+}
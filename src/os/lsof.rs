@@ -0,0 +1,59 @@
+use ::std::process::Command;
+
+use crate::network::{LocalSocket, Protocol, ProcessInfo};
+use crate::OpenSockets;
+
+/// Enumerates open sockets on macOS/FreeBSD by shelling out to `lsof`,
+/// which is the only portable way to get this information on those
+/// platforms without writing a separate kernel-specific backend per OS.
+pub fn get_open_sockets() -> OpenSockets {
+    let mut open_sockets = OpenSockets::new();
+
+    let output = match Command::new("lsof")
+        .args(&["-i", "-n", "-P", "-F", "pcPnT"])
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => return open_sockets,
+    };
+    if !output.status.success() {
+        return open_sockets;
+    }
+
+    let mut pid = None;
+    let mut name = None;
+    let mut protocol = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (tag, rest) = line.split_at(1);
+        match tag {
+            "p" => pid = rest.parse::<i32>().ok(),
+            "c" => name = Some(rest.to_string()),
+            "P" => {
+                protocol = match rest {
+                    "TCP" => Some(Protocol::Tcp),
+                    "UDP" => Some(Protocol::Udp),
+                    _ => None,
+                }
+            }
+            "n" => {
+                if let (Some(pid), Some(name), Some(protocol)) = (pid, name.clone(), protocol) {
+                    if let Some(local_socket) = parse_name_field(rest, protocol) {
+                        open_sockets.insert(local_socket, ProcessInfo { name, pid });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    open_sockets
+}
+
+fn parse_name_field(field: &str, protocol: Protocol) -> Option<LocalSocket> {
+    let addr_part = field.split(' ').next().unwrap_or(field);
+    let local_part = addr_part.split("->").next()?;
+    let (ip_str, port_str) = local_part.rsplit_once(':')?;
+    let ip = ip_str.trim_start_matches('[').trim_end_matches(']').parse().ok()?;
+    let port = port_str.parse().ok()?;
+    Some(LocalSocket { ip, port, protocol })
+}
@@ -0,0 +1,10 @@
+pub mod pcap_replay;
+pub mod shared;
+
+#[cfg(target_os = "linux")]
+pub mod linux;
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub mod lsof;
+
+pub use pcap_replay::get_input as get_replay_input;
+pub use shared::get_input;
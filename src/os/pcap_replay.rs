@@ -0,0 +1,296 @@
+use ::std::convert::TryInto;
+use ::std::fs;
+use ::std::io::{self, ErrorKind};
+use ::std::path::Path;
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+
+use ::pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::network::LinkLayer;
+use crate::os::shared::{create_write_to_stdout, sigwinch, KeyboardEvents};
+use crate::{OpenSockets, OsInputOutput};
+
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+const LINKTYPE_NULL: u32 = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Endianness {
+    Little,
+    Big,
+}
+
+/// Replays a classic (non-`pcapng`) libpcap capture file as if it were a
+/// live `DataLinkReceiver`, so the full parsing/UI pipeline can be driven
+/// from a file instead of a real interface. `pcapng` isn't supported yet;
+/// re-save such captures as classic pcap (e.g. `tshark -F pcap`) first.
+pub struct PcapFileReceiver {
+    data: Vec<u8>,
+    offset: usize,
+    endianness: Endianness,
+    nanosecond_resolution: bool,
+    pace: bool,
+    /// `LINKTYPE_NULL` prefixes each frame with a 4-byte host-byte-order
+    /// address-family header before the IP header; strip it so downstream
+    /// parsing sees the same thing it would for `LinkLayer::Raw` elsewhere.
+    strip_null_header: bool,
+    first_packet_ts: Option<Duration>,
+    replay_started_at: Option<Instant>,
+    current: Vec<u8>,
+}
+
+impl PcapFileReceiver {
+    /// Opens `path` and reads its global header, returning the interface's
+    /// apparent `LinkLayer` alongside the receiver. When `pace` is set,
+    /// `next()` sleeps between packets to honour the capture's original
+    /// timing; otherwise packets are delivered as fast as possible.
+    fn open(path: &Path, pace: bool) -> io::Result<(LinkLayer, Self)> {
+        let data = fs::read(path)?;
+        if data.len() < 24 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "truncated pcap global header"));
+        }
+
+        let (endianness, nanosecond_resolution) = match u32::from_le_bytes(data[0..4].try_into().unwrap()) {
+            MAGIC_MICROS => (Endianness::Little, false),
+            MAGIC_NANOS => (Endianness::Little, true),
+            _ => match u32::from_be_bytes(data[0..4].try_into().unwrap()) {
+                MAGIC_MICROS => (Endianness::Big, false),
+                MAGIC_NANOS => (Endianness::Big, true),
+                _ => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "not a pcap file, or an unsupported pcapng capture",
+                    ))
+                }
+            },
+        };
+
+        let linktype = read_u32(&data, 20, endianness);
+        let link_layer = link_layer_for_linktype(linktype);
+        Ok((
+            link_layer,
+            PcapFileReceiver {
+                data,
+                offset: 24,
+                endianness,
+                nanosecond_resolution,
+                pace,
+                strip_null_header: linktype == LINKTYPE_NULL,
+                first_packet_ts: None,
+                replay_started_at: None,
+                current: Vec::new(),
+            },
+        ))
+    }
+
+    /// Sleeps, if pacing, so that the gap between this packet and the first
+    /// one replayed matches the gap recorded in the capture.
+    fn wait_for(&mut self, ts_sec: u32, ts_frac: u32) {
+        let frac_nanos = if self.nanosecond_resolution { ts_frac } else { ts_frac * 1_000 };
+        let ts = Duration::new(u64::from(ts_sec), frac_nanos);
+
+        let (first_ts, started_at) = match (self.first_packet_ts, self.replay_started_at) {
+            (Some(first_ts), Some(started_at)) => (first_ts, started_at),
+            _ => {
+                self.first_packet_ts = Some(ts);
+                self.replay_started_at = Some(Instant::now());
+                return;
+            }
+        };
+
+        if let Some(elapsed_in_capture) = ts.checked_sub(first_ts) {
+            let due_at = started_at + elapsed_in_capture;
+            let now = Instant::now();
+            if due_at > now {
+                thread::sleep(due_at - now);
+            }
+        }
+    }
+}
+
+fn read_u32(data: &[u8], at: usize, endianness: Endianness) -> u32 {
+    let bytes = data[at..at + 4].try_into().unwrap();
+    match endianness {
+        Endianness::Little => u32::from_le_bytes(bytes),
+        Endianness::Big => u32::from_be_bytes(bytes),
+    }
+}
+
+/// Maps a pcap `LINKTYPE_*` value to the `LinkLayer` the packet parser
+/// needs; anything we don't recognise defaults to `Ethernet`, which covers
+/// the vast majority of captures.
+fn link_layer_for_linktype(linktype: u32) -> LinkLayer {
+    match linktype {
+        // LINKTYPE_NULL (BSD loopback): `PcapFileReceiver::next()` strips
+        // its 4-byte header before yielding frames, so by the time the
+        // parser sees them they're headerless like any other Raw link.
+        LINKTYPE_NULL => LinkLayer::Raw,
+        101 => LinkLayer::Raw,    // LINKTYPE_RAW
+        113 => LinkLayer::Cooked, // LINKTYPE_LINUX_SLL
+        _ => LinkLayer::Ethernet,
+    }
+}
+
+impl DataLinkReceiver for PcapFileReceiver {
+    fn next(&mut self) -> io::Result<&[u8]> {
+        if self.offset + 16 > self.data.len() {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "end of pcap file"));
+        }
+        let ts_sec = read_u32(&self.data, self.offset, self.endianness);
+        let ts_frac = read_u32(&self.data, self.offset + 4, self.endianness);
+        let incl_len = read_u32(&self.data, self.offset + 8, self.endianness) as usize;
+        self.offset += 16;
+
+        if self.offset + incl_len > self.data.len() {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "truncated packet record"));
+        }
+        let mut start = self.offset;
+        let end = self.offset + incl_len;
+        self.offset = end;
+        if self.strip_null_header && end - start >= 4 {
+            start += 4;
+        }
+        self.current = self.data[start..end].to_vec();
+
+        if self.pace {
+            self.wait_for(ts_sec, ts_frac);
+        }
+        Ok(&self.current)
+    }
+}
+
+/// There are no live sockets to enumerate in replay mode.
+fn no_open_sockets() -> OpenSockets {
+    OpenSockets::new()
+}
+
+/// Builds an `OsInputOutput` that replays `path` instead of listening on a
+/// live interface, for post-hoc analysis of captures made elsewhere (and a
+/// deterministic, root-free way to feed real traffic through the parsing
+/// and UI pipeline in tests). `get_open_sockets` and `dns_client` are
+/// stubbed out, since there's nothing live to ask.
+pub fn get_input(path: &Path, realtime: bool) -> Result<OsInputOutput, failure::Error> {
+    let (link_layer, receiver) = PcapFileReceiver::open(path, realtime)?;
+
+    let keyboard_events = Box::new(KeyboardEvents);
+    let write_to_stdout = create_write_to_stdout();
+    let (on_winch, cleanup) = sigwinch();
+
+    Ok(OsInputOutput {
+        network_interfaces: Vec::new(),
+        network_frames: vec![(link_layer, Box::new(receiver) as Box<dyn DataLinkReceiver>)],
+        interface_updates: None,
+        get_open_sockets: no_open_sockets,
+        keyboard_events,
+        dns_client: None,
+        on_winch,
+        cleanup,
+        write_to_stdout,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_u32(buf: &mut Vec<u8>, v: u32, big: bool) {
+        if big {
+            buf.extend_from_slice(&v.to_be_bytes());
+        } else {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    fn global_header(magic: u32, big: bool, linktype: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        push_u32(&mut buf, magic, big);
+        buf.extend_from_slice(&[0, 0, 0, 0]); // version_major, version_minor
+        push_u32(&mut buf, 0, big); // thiszone
+        push_u32(&mut buf, 0, big); // sigfigs
+        push_u32(&mut buf, 0, big); // snaplen
+        push_u32(&mut buf, linktype, big);
+        buf
+    }
+
+    fn record(ts_sec: u32, ts_frac: u32, payload: &[u8], big: bool) -> Vec<u8> {
+        let mut buf = Vec::new();
+        push_u32(&mut buf, ts_sec, big);
+        push_u32(&mut buf, ts_frac, big);
+        push_u32(&mut buf, payload.len() as u32, big); // incl_len
+        push_u32(&mut buf, payload.len() as u32, big); // orig_len
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    fn write_temp_pcap(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "bandwhich-pcap-replay-test-{}-{}.pcap",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn detects_little_endian_microsecond_header() {
+        let mut data = global_header(MAGIC_MICROS, false, 1 /* LINKTYPE_ETHERNET */);
+        data.extend(record(0, 0, &[0xaa, 0xbb, 0xcc, 0xdd], false));
+        let path = write_temp_pcap("little-endian-micros", &data);
+
+        let (link_layer, receiver) = PcapFileReceiver::open(&path, false).unwrap();
+
+        assert_eq!(receiver.endianness, Endianness::Little);
+        assert!(!receiver.nanosecond_resolution);
+        assert_eq!(link_layer, LinkLayer::Ethernet);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn detects_big_endian_nanosecond_header() {
+        let mut data = global_header(MAGIC_NANOS, true, 1 /* LINKTYPE_ETHERNET */);
+        data.extend(record(0, 0, &[0xaa, 0xbb, 0xcc, 0xdd], true));
+        let path = write_temp_pcap("big-endian-nanos", &data);
+
+        let (_, receiver) = PcapFileReceiver::open(&path, false).unwrap();
+
+        assert_eq!(receiver.endianness, Endianness::Big);
+        assert!(receiver.nanosecond_resolution);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn strips_linktype_null_header_before_yielding_frames() {
+        let mut data = global_header(MAGIC_MICROS, false, LINKTYPE_NULL);
+        let payload = [0x02, 0x00, 0x00, 0x00, 0xde, 0xad, 0xbe, 0xef];
+        data.extend(record(0, 0, &payload, false));
+        let path = write_temp_pcap("linktype-null", &data);
+
+        let (link_layer, mut receiver) = PcapFileReceiver::open(&path, false).unwrap();
+        assert_eq!(link_layer, LinkLayer::Raw);
+
+        let frame = receiver.next().unwrap();
+        assert_eq!(frame, &[0xde, 0xad, 0xbe, 0xef]);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn truncated_packet_record_is_an_error() {
+        let mut data = global_header(MAGIC_MICROS, false, 1 /* LINKTYPE_ETHERNET */);
+        // Claim a 16-byte payload but only supply 4, so the record is
+        // truncated mid-frame.
+        push_u32(&mut data, 0, false); // ts_sec
+        push_u32(&mut data, 0, false); // ts_usec
+        push_u32(&mut data, 16, false); // incl_len
+        push_u32(&mut data, 16, false); // orig_len
+        data.extend_from_slice(&[0xaa, 0xbb, 0xcc, 0xdd]);
+        let path = write_temp_pcap("truncated-record", &data);
+
+        let (_, mut receiver) = PcapFileReceiver::open(&path, false).unwrap();
+        let err = receiver.next().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let _ = fs::remove_file(path);
+    }
+}
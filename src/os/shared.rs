@@ -1,4 +1,4 @@
-use ::pnet_bandwhich_fork::datalink::Channel::Ethernet;
+use ::pnet_bandwhich_fork::datalink::Channel::{Ethernet, Ip};
 use ::pnet_bandwhich_fork::datalink::DataLinkReceiver;
 use ::pnet_bandwhich_fork::datalink::{self, Config, NetworkInterface};
 use ::std::io::{self, stdin, Write};
@@ -15,7 +15,10 @@ use signal_hook::iterator::Signals;
 use crate::os::linux::get_open_sockets;
 #[cfg(any(target_os = "macos", target_os = "freebsd"))]
 use crate::os::lsof::get_open_sockets;
-use crate::{network::dns, OsInputOutput};
+use crate::{
+    network::{dns, LinkLayer},
+    OsInputOutput,
+};
 
 pub type OnSigWinch = dyn Fn(Box<dyn Fn()>) + Send;
 pub type SigCleanup = dyn Fn() + Send;
@@ -32,14 +35,46 @@ impl Iterator for KeyboardEvents {
     }
 }
 
-fn get_datalink_channel(
+/// Linux exposes a pseudo-interface named `any` that cooked-captures every
+/// interface at once, prefixing each frame with a fixed-size `SLL` header
+/// instead of the interface's native link-layer header.
+const ANY_INTERFACE_NAME: &str = "any";
+
+fn link_layer_for(interface: &NetworkInterface) -> LinkLayer {
+    if interface.name == ANY_INTERFACE_NAME {
+        LinkLayer::Cooked
+    } else if interface.is_loopback() || interface.is_point_to_point() {
+        LinkLayer::Raw
+    } else {
+        LinkLayer::Ethernet
+    }
+}
+
+/// `Channel::Ip` is pnet's own classification for a channel with no L2
+/// header at all, so unlike `link_layer_for`, this never falls back to
+/// `LinkLayer::Ethernet` -- a tun device or IP tunnel that pnet routes to
+/// `Ip` without setting the point-to-point flag would otherwise have a
+/// phantom 14-byte Ethernet header stripped off its real IP header.
+fn link_layer_for_ip_channel(interface: &NetworkInterface) -> LinkLayer {
+    if interface.name == ANY_INTERFACE_NAME {
+        LinkLayer::Cooked
+    } else {
+        LinkLayer::Raw
+    }
+}
+
+pub(crate) fn get_datalink_channel(
     interface: &NetworkInterface,
-) -> Result<Box<dyn DataLinkReceiver>, std::io::Error> {
+) -> Result<(LinkLayer, Box<dyn DataLinkReceiver>), std::io::Error> {
     let mut config = Config::default();
     config.read_timeout = Some(time::Duration::new(1, 0));
 
     match datalink::channel(interface, config) {
-        Ok(Ethernet(_tx, rx)) => Ok(rx),
+        Ok(Ethernet(_tx, rx)) => Ok((link_layer_for(interface), rx)),
+        // pnet hands back a Layer-3 channel for interfaces that don't have
+        // an Ethernet header at all, e.g. loopback, tun/tap, PPP, and the
+        // Linux `any` cooked-capture device.
+        Ok(Ip(_tx, rx)) => Ok((link_layer_for_ip_channel(interface), rx)),
         Ok(_) => Err(std::io::Error::new(
             ErrorKind::Other,
             "Unsupported interface type",
@@ -54,7 +89,26 @@ fn get_interface(interface_name: &str) -> Option<NetworkInterface> {
         .find(|iface| iface.name == interface_name)
 }
 
-fn sigwinch() -> (Box<OnSigWinch>, Box<SigCleanup>) {
+/// Starts the live interface registry, if this platform has one. Linux
+/// drives it off `NETLINK_ROUTE`; other platforms fall back to the static
+/// startup snapshot until an equivalent watcher is written for them.
+#[cfg(target_os = "linux")]
+fn spawn_interface_watcher(
+    already_open: &[NetworkInterface],
+) -> Option<::std::sync::mpsc::Receiver<crate::network::InterfaceUpdate>> {
+    Some(crate::os::linux::spawn_interface_watcher(
+        already_open.iter().map(|iface| iface.name.clone()),
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn spawn_interface_watcher(
+    _already_open: &[NetworkInterface],
+) -> Option<::std::sync::mpsc::Receiver<crate::network::InterfaceUpdate>> {
+    None
+}
+
+pub(crate) fn sigwinch() -> (Box<OnSigWinch>, Box<SigCleanup>) {
     let signals = Signals::new(&[signal_hook::SIGWINCH]).unwrap();
     let on_winch = {
         let signals = signals.clone();
@@ -73,7 +127,7 @@ fn sigwinch() -> (Box<OnSigWinch>, Box<SigCleanup>) {
     (Box::new(on_winch), Box::new(cleanup))
 }
 
-fn create_write_to_stdout() -> Box<dyn FnMut(String) + Send> {
+pub(crate) fn create_write_to_stdout() -> Box<dyn FnMut(String) + Send> {
     Box::new({
         let mut stdout = io::stdout();
         move |output: String| {
@@ -107,15 +161,15 @@ pub fn get_input(
         let network_frames = network_frames.clone();
         let mut available_network_frames = Vec::new();
         let mut available_interfaces: Vec<NetworkInterface> = Vec::new();
-        for (iface, rx) in network_frames.filter_map(|(iface, channel)| {
-            if let Ok(rx) = channel {
-                Some((iface, rx))
+        for (iface, link_layer, rx) in network_frames.filter_map(|(iface, channel)| {
+            if let Ok((link_layer, rx)) = channel {
+                Some((iface, link_layer, rx))
             } else {
                 None
             }
         }) {
             available_interfaces.push(iface.clone());
-            available_network_frames.push(rx);
+            available_network_frames.push((link_layer, rx));
         }
         (available_network_frames, available_interfaces)
     };
@@ -131,6 +185,8 @@ pub fn get_input(
         failure::bail!("Failed to find any network interface to listen on.");
     }
 
+    let interface_updates = spawn_interface_watcher(&network_interfaces);
+
     let keyboard_events = Box::new(KeyboardEvents);
     let write_to_stdout = create_write_to_stdout();
     let (on_winch, cleanup) = sigwinch();
@@ -149,6 +205,7 @@ pub fn get_input(
     Ok(OsInputOutput {
         network_interfaces,
         network_frames: available_network_frames,
+        interface_updates,
         get_open_sockets,
         keyboard_events,
         dns_client,
@@ -174,6 +231,13 @@ fn eperm_message() -> &'static str {
     * Try running `bandwhich` with `sudo`
 
     * Build a `setcap(8)` wrapper for `bandwhich` with the following rules:
-        `cap_sys_ptrace,cap_dac_read_search,cap_net_raw,cap_net_admin+ep`
+        `cap_net_raw,cap_net_admin+ep`
+
+      `get_open_sockets` talks to the kernel over `NETLINK_SOCK_DIAG` and only
+      needs `cap_dac_read_search` on top of that if you want to see sockets
+      owned by other users; `cap_sys_ptrace` is no longer required. If
+      `NETLINK_SOCK_DIAG` is unavailable (e.g. disabled in a container), we
+      fall back to scanning `/proc/net`, which attributes sockets to their
+      owners the same way.
     "#
 }